@@ -6,7 +6,14 @@ use crate::set::*;
 pub struct FuzzyEngine<const N: usize, const M: usize> {
     inputs_var: [LinguisticVar; N],
     outputs_var: [LinguisticVar; M],
-    rules: Vec<(Vec<String>, Vec<String>)>, // list of ([input1_term, input2_term, ...] -> output_term)
+    rules: Vec<(Vec<String>, Consequent<N, M>)>, // list of ([input1_term, input2_term, ...] -> output)
+    defuzz_method: Defuzzification,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Consequent<const N: usize, const M: usize> {
+    Terms(Vec<String>),               // mamdani, output terms
+    Linear([LinearConsequent<N>; M]), // sugeno, linear function per output
 }
 
 impl<const N: usize, const M: usize> FuzzyEngine<N, M> {
@@ -17,55 +24,274 @@ impl<const N: usize, const M: usize> FuzzyEngine<N, M> {
         FuzzyEngine {
             inputs_var,
             outputs_var: output_var,
-            rules: Vec::<(Vec<String>, Vec<String>)>::new(),
+            rules: Vec::<(Vec<String>, Consequent<N, M>)>::new(),
+            defuzz_method: Defuzzification::default(),
+        }
+    }
+
+    pub fn set_defuzz_method(&mut self, method: Defuzzification) {
+        self.defuzz_method = method;
+    }
+
+    /// The inference mode follows the kind of rules added, an engine without
+    /// rules counts as Mamdani.
+    pub fn inference(&self) -> Inference {
+        match self.rules.first() {
+            Some((_, Consequent::Linear(_))) => Inference::Sugeno,
+            _ => Inference::Mamdani,
         }
     }
 
     pub fn add_rule(&mut self, cond: [&str; N], res: [&str; M]) {
-        for i in 0..self.inputs_var.len() {
-            self.inputs_var[i].term(&cond[i]); // check if term "cond[i]" exist
+        for (var, term) in self.outputs_var.iter().zip(res.iter()) {
+            var.term(term); // term() check if term "res" is exist
         }
-        for i in 0..self.outputs_var.len() {
-            self.outputs_var[i].term(&res[i]); // term() check if term "res" is exist
+
+        let results: Vec<String> = res.iter().map(|x| x.to_string()).collect();
+        self.push_rule(cond, Consequent::Terms(results));
+    }
+
+    pub fn add_sugeno_rule(&mut self, cond: [&str; N], res: [LinearConsequent<N>; M]) {
+        self.push_rule(cond, Consequent::Linear(res));
+    }
+
+    fn push_rule(&mut self, cond: [&str; N], res: Consequent<N, M>) {
+        for (var, term) in self.inputs_var.iter().zip(cond.iter()) {
+            var.term(term); // check if term "cond[i]" exist
+        }
+        if let Some((_, first)) = self.rules.first() {
+            if matches!(first, Consequent::Linear(_)) != matches!(res, Consequent::Linear(_)) {
+                panic!("can not mix mamdani and sugeno rules in one engine");
+            }
         }
 
         let conditions: Vec<String> = cond.iter().map(|x| x.to_string()).collect();
-        let results: Vec<String> = res.iter().map(|x| x.to_string()).collect();
-        self.rules.push((conditions, results));
+        self.rules.push((conditions, res));
+    }
+
+    fn firing_strength(&self, cond: &[String], inputs: &[f64; N]) -> f64 {
+        let mut aj = f64::MAX;
+        for ((var, term), x) in self.inputs_var.iter().zip(cond.iter()).zip(inputs.iter()) {
+            aj = aj.min(var.term(term).degree_of(*x));
+        }
+        aj
     }
 
     pub fn calculate(&self, inputs: [f64; N]) -> Vec<FuzzySet> {
-        let mut temp: Vec<Vec<FuzzySet>> = vec![];
-        for j in 0..self.rules.len() {
-            let mut aj = f64::MAX;
-            for i in 0..self.rules[j].0.len() {
-                let fuzzy_set = self.inputs_var[i].term(&self.rules[j].0[i]);
-                let v = fuzzy_set.degree_of(inputs[i]);
-                aj = aj.min(v);
+        // start from empty sets so an engine without rules defuzzifies to 0.0
+        let mut res: Vec<FuzzySet> = self
+            .outputs_var
+            .iter()
+            .map(|var| FuzzySet {
+                name: "".into(),
+                universe: var.universe.clone(),
+                membership: vec![0.0; var.universe.len()],
+            })
+            .collect();
+        for (j, (cond, res_j)) in self.rules.iter().enumerate() {
+            let terms = match res_j {
+                Consequent::Terms(terms) => terms,
+                Consequent::Linear(_) => panic!("sugeno rules need calculate_sugeno()"),
+            };
+            let aj = self.firing_strength(cond, &inputs);
+            for (i, term) in terms.iter().enumerate() {
+                let set = self.outputs_var[i].term(term).min(aj, format!("f{}", j));
+                res[i] = res[i].std_union(&set, "".into());
             }
+        }
+        res
+    }
 
-            let mut t: Vec<FuzzySet> = vec![];
-            for i in 0..self.rules[j].1.len() {
-                t.push(
-                    self.outputs_var[i]
-                        .term(&self.rules[j].1[i])
-                        .min(aj, format!("f{}", j)),
-                );
+    /// Weighted average of the rule consequents, weighted by firing strength.
+    pub fn calculate_sugeno(&self, inputs: [f64; N]) -> Vec<f64> {
+        let mut top_sum = vec![0.0; M];
+        let mut bot_sum = 0.0;
+        for (cond, res) in self.rules.iter() {
+            let res = match res {
+                Consequent::Linear(res) => res,
+                Consequent::Terms(_) => panic!("mamdani rules need calculate()"),
+            };
+            let aj = self.firing_strength(cond, &inputs);
+            for (s, f) in top_sum.iter_mut().zip(res.iter()) {
+                *s += aj * f.eval(&inputs);
             }
+            bot_sum += aj;
+        }
+        if bot_sum == 0.0 {
+            return vec![0.0; M];
+        }
+        top_sum.iter().map(|s| s / bot_sum).collect()
+    }
+
+    pub fn defuzz(&self, inputs: [f64; N]) -> Vec<f64> {
+        match self.inference() {
+            Inference::Mamdani => self
+                .calculate(inputs)
+                .iter()
+                .map(|set| set.defuzz(self.defuzz_method))
+                .collect(),
+            Inference::Sugeno => self.calculate_sugeno(inputs),
+        }
+    }
 
-            temp.push(t);
+    /// Checks one inference for signs of a broken preset: NaN inputs, no rule
+    /// firing at all, and outputs of defuzz() that fall outside their universe.
+    pub fn anomalies(&self, inputs: [f64; N], outputs: &[f64]) -> Vec<Anomaly> {
+        if outputs.len() != M {
+            panic!("expected {} outputs, got {}", M, outputs.len());
         }
-        let mut res: Vec<FuzzySet> = vec![];
-        for i in 0..M {
-            res.push(temp[0][i].std_union(&temp[0][i], "".into()));
+
+        let mut res: Vec<Anomaly> = vec![];
+        for (i, x) in inputs.iter().enumerate() {
+            if x.is_nan() {
+                res.push(Anomaly::NanInput(i));
+            }
         }
-        for j in 1..temp.len() {
-            for i in 0..M {
-                res[i] = res[i].std_union(&temp[j][i], "".into());
+
+        let fired = self
+            .rules
+            .iter()
+            .any(|(cond, _)| self.firing_strength(cond, &inputs) > 0.0);
+        if !fired {
+            res.push(Anomaly::NoRuleFired);
+        }
+
+        for (i, (var, &v)) in self.outputs_var.iter().zip(outputs.iter()).enumerate() {
+            let lo = var.universe[0];
+            let hi = var.universe[var.universe.len() - 1];
+            if !(lo..=hi).contains(&v) {
+                res.push(Anomaly::OutputOutOfUniverse(i, v));
             }
         }
         res
     }
+
+    /// Looks for rules that can be dropped or need attention, using `history` as
+    /// the set of input samples the rule base is expected to see.
+    ///
+    /// A Mamdani rule is dominated when another rule has the same consequents and
+    /// fires at least as strongly on every sample, so it never changes the
+    /// aggregated output. Sugeno rules are never dominated: the output is a
+    /// weighted average, so dropping any firing rule, even an exact duplicate,
+    /// shifts the weights and changes the result.
+    /// A rule is unreachable when its antecedent terms never co-activate. Each
+    /// dominated rule is paired with a dominating rule that is not itself removed.
+    pub fn analyze_rules(&self, history: &[[f64; N]]) -> RuleAnalysis {
+        if history.is_empty() {
+            panic!("history can not be empty");
+        }
+
+        let strengths: Vec<Vec<f64>> = self
+            .rules
+            .iter()
+            .map(|(cond, _)| {
+                history
+                    .iter()
+                    .map(|inputs| self.firing_strength(cond, inputs))
+                    .collect()
+            })
+            .collect();
+
+        let unreachable: Vec<bool> = strengths
+            .iter()
+            .map(|s| s.iter().all(|&v| v == 0.0))
+            .collect();
+        let dominates = |k: usize, j: usize| {
+            let covered = strengths[j]
+                .iter()
+                .zip(strengths[k].iter())
+                .all(|(sj, sk)| sj <= sk);
+            let (res_j, res_k) = (&self.rules[j].1, &self.rules[k].1);
+            match res_j {
+                // identical strengths dominate each other, keep the earlier rule
+                Consequent::Terms(_) => {
+                    j != k && res_j == res_k && covered && (k < j || strengths[j] != strengths[k])
+                }
+                Consequent::Linear(_) => false,
+            }
+        };
+        let dominated: Vec<bool> = (0..self.rules.len())
+            .map(|j| (0..self.rules.len()).any(|k| dominates(k, j)))
+            .collect();
+
+        let mut analysis = RuleAnalysis::default();
+        for (j, (cond_j, res_j)) in self.rules.iter().enumerate() {
+            for (k, (cond_k, res_k)) in self.rules.iter().enumerate().skip(j + 1) {
+                if cond_j == cond_k && res_j != res_k {
+                    analysis.conflicts.push((j, k));
+                }
+            }
+
+            // unreachable rules are removed anyway, and could only be covered by
+            // other unreachable rules
+            if unreachable[j] {
+                analysis.unreachable.push(j);
+                continue;
+            }
+            // dominance is transitive, so a dominated rule is always covered by
+            // some rule that is not dominated itself
+            if dominated[j] {
+                if let Some(k) = (0..self.rules.len()).find(|&k| !dominated[k] && dominates(k, j)) {
+                    analysis.dominated.push((j, k));
+                }
+            }
+        }
+
+        analysis.suggested_removals = analysis
+            .dominated
+            .iter()
+            .map(|(j, _)| *j)
+            .chain(analysis.unreachable.iter().copied())
+            .collect();
+        analysis.suggested_removals.sort_unstable();
+        analysis.suggested_removals.dedup();
+        analysis
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Inference {
+    #[default]
+    Mamdani,
+    Sugeno, // Takagi-Sugeno-Kang, rule consequents are linear functions of the inputs
+}
+
+/// Sugeno rule output `coefficients[0] * x1 + coefficients[1] * x2 + ... + constant`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearConsequent<const N: usize> {
+    pub coefficients: [f64; N],
+    pub constant: f64,
+}
+
+impl<const N: usize> LinearConsequent<N> {
+    pub fn eval(&self, inputs: &[f64; N]) -> f64 {
+        self.coefficients
+            .iter()
+            .zip(inputs.iter())
+            .fold(self.constant, |s, (a, x)| s + a * x)
+    }
+}
+
+pub fn linear<const N: usize>(coefficients: [f64; N], constant: f64) -> LinearConsequent<N> {
+    LinearConsequent {
+        coefficients,
+        constant,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anomaly {
+    NanInput(usize),                 // index of the input
+    NoRuleFired,                     // no rule covers the inputs
+    OutputOutOfUniverse(usize, f64), // index of the output, defuzzified value
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RuleAnalysis {
+    pub dominated: Vec<(usize, usize)>, // (rule, rule that dominates it)
+    pub conflicts: Vec<(usize, usize)>, // same antecedents with different consequents
+    pub unreachable: Vec<usize>,
+    pub suggested_removals: Vec<usize>,
 }
 
 #[cfg(test)]
@@ -85,6 +311,198 @@ mod tests {
         );
 
         let mut f_engine = FuzzyEngine::new([rsi.clone()], [rsi]);
-        f_engine.add_rule(["medium".into()], ["low".into()]);
+        f_engine.add_rule(["medium"], ["low"]);
+    }
+
+    #[test]
+    fn test_analyze_rules() {
+        let rsi = LinguisticVar::new(
+            vec![
+                (&triangular(20f64, 1.0, 20f64), "low"),
+                (&triangular(20f64, 1.0, 40f64), "wide"),
+                (&triangular(50f64, 1.0, 5f64), "medium"),
+                (&triangular(80f64, 1.0, 20f64), "high"),
+            ],
+            arange(0f64, 100f64, 0.01),
+        );
+
+        let mut f_engine = FuzzyEngine::new([rsi.clone()], [rsi.clone()]);
+        f_engine.add_rule(["low"], ["high"]);
+        f_engine.add_rule(["low"], ["low"]);
+        f_engine.add_rule(["high"], ["low"]);
+        f_engine.add_rule(["high"], ["low"]);
+        f_engine.add_rule(["medium"], ["high"]);
+
+        let analysis = f_engine.analyze_rules(&[[10.0], [90.0]]);
+        assert_eq!(analysis.dominated, vec![(3, 2)]);
+        assert_eq!(analysis.conflicts, vec![(0, 1)]);
+        assert_eq!(analysis.unreachable, vec![4]);
+        assert_eq!(analysis.suggested_removals, vec![3, 4]);
+
+        // 1 duplicates 0 and both are covered by 2, point both at the kept rule
+        let mut f_engine = FuzzyEngine::new([rsi.clone()], [rsi]);
+        f_engine.add_rule(["low"], ["high"]);
+        f_engine.add_rule(["low"], ["high"]);
+        f_engine.add_rule(["wide"], ["high"]);
+
+        let analysis = f_engine.analyze_rules(&[[10.0], [30.0]]);
+        assert_eq!(analysis.dominated, vec![(0, 2), (1, 2)]);
+        assert_eq!(analysis.suggested_removals, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let rsi = LinguisticVar::new(
+            vec![
+                (&triangular(20f64, 1.0, 20f64), "low"),
+                (&triangular(80f64, 1.0, 20f64), "high"),
+            ],
+            arange(0f64, 100f64, 0.01),
+        );
+        let signal = LinguisticVar::new(
+            vec![(&triangular(50f64, 1.0, 10f64), "buy")],
+            arange(40f64, 60f64, 0.01),
+        );
+
+        let mut f_engine = FuzzyEngine::new([rsi], [signal]);
+        f_engine.add_rule(["low"], ["buy"]);
+
+        let outputs = f_engine.defuzz([10.0]);
+        assert_eq!(f_engine.anomalies([10.0], &outputs), vec![]);
+
+        let outputs = f_engine.defuzz([90.0]);
+        assert_eq!(
+            f_engine.anomalies([90.0], &outputs),
+            vec![Anomaly::NoRuleFired, Anomaly::OutputOutOfUniverse(0, 0.0)]
+        );
+
+        let outputs = f_engine.defuzz([f64::NAN]);
+        assert!(f_engine
+            .anomalies([f64::NAN], &outputs)
+            .contains(&Anomaly::NanInput(0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_anomalies_missing_outputs() {
+        let rsi = LinguisticVar::new(
+            vec![(&triangular(20f64, 1.0, 20f64), "low")],
+            arange(0f64, 100f64, 0.01),
+        );
+
+        let mut f_engine = FuzzyEngine::new([rsi.clone()], [rsi]);
+        f_engine.add_rule(["low"], ["low"]);
+        f_engine.anomalies([10.0], &[]);
+    }
+
+    #[test]
+    fn test_defuzz_method() {
+        let rsi = LinguisticVar::new(
+            vec![
+                (&triangular(20f64, 1.0, 20f64), "low"),
+                (&triangular(80f64, 1.0, 20f64), "high"),
+            ],
+            arange(0f64, 100f64, 1.0),
+        );
+        let signal = LinguisticVar::new(
+            vec![(&trapezoidal(20.0, 40.0, 50.0, 90.0, 1.0), "buy")],
+            arange(0f64, 100f64, 1.0),
+        );
+
+        let mut f_engine = FuzzyEngine::new([rsi], [signal]);
+        f_engine.add_rule(["low"], ["buy"]);
+
+        assert_eq!(
+            f_engine.defuzz([20.0]),
+            vec![f_engine.calculate([20.0])[0].centroid_defuzz()]
+        );
+        f_engine.set_defuzz_method(Defuzzification::SmallestOfMaximum);
+        assert_eq!(f_engine.defuzz([20.0]), vec![40.0]);
+        f_engine.set_defuzz_method(Defuzzification::LargestOfMaximum);
+        assert_eq!(f_engine.defuzz([20.0]), vec![50.0]);
+
+        let rsi = LinguisticVar::new(
+            vec![(&triangular(20f64, 1.0, 20f64), "low")],
+            arange(0f64, 100f64, 1.0),
+        );
+        let f_engine = FuzzyEngine::new([rsi.clone()], [rsi]);
+        let outputs = f_engine.defuzz([20.0]);
+        assert_eq!(outputs, vec![0.0]);
+        assert_eq!(
+            f_engine.anomalies([20.0], &outputs),
+            vec![Anomaly::NoRuleFired]
+        );
+    }
+
+    #[test]
+    fn test_sugeno() {
+        let rsi = LinguisticVar::new(
+            vec![
+                (&triangular(20f64, 1.0, 40f64), "low"),
+                (&triangular(80f64, 1.0, 40f64), "high"),
+            ],
+            arange(0f64, 100f64, 1.0),
+        );
+        // sugeno outputs only need a universe, no terms
+        let signal = LinguisticVar::new(vec![], arange(-50f64, 100f64, 1.0));
+
+        let mut f_engine = FuzzyEngine::new([rsi.clone()], [signal.clone()]);
+        assert_eq!(f_engine.inference(), Inference::Mamdani);
+        f_engine.add_sugeno_rule(["low"], [linear([0.0], 100.0)]);
+        f_engine.add_sugeno_rule(["high"], [linear([-2.0], 100.0)]);
+        assert_eq!(f_engine.inference(), Inference::Sugeno);
+
+        assert_eq!(f_engine.defuzz([20.0]), vec![100.0]);
+        assert_eq!(f_engine.defuzz([80.0]), vec![-60.0]);
+        // low and high both fire with 0.25
+        assert_eq!(f_engine.defuzz([50.0]), vec![50.0]);
+        assert_eq!(f_engine.defuzz([50.0]), f_engine.calculate_sugeno([50.0]));
+
+        let outputs = f_engine.defuzz([80.0]);
+        assert_eq!(
+            f_engine.anomalies([80.0], &outputs),
+            vec![Anomaly::OutputOutOfUniverse(0, -60.0)]
+        );
+    }
+
+    #[test]
+    fn test_analyze_sugeno_rules() {
+        let rsi = LinguisticVar::new(
+            vec![
+                (&triangular(20f64, 1.0, 40f64), "low"),
+                (&triangular(80f64, 1.0, 40f64), "high"),
+            ],
+            arange(0f64, 100f64, 1.0),
+        );
+        let signal = LinguisticVar::new(vec![], arange(-100f64, 100f64, 1.0));
+
+        let mut f_engine = FuzzyEngine::new([rsi], [signal]);
+        f_engine.add_sugeno_rule(["low"], [linear([0.0], 100.0)]);
+        f_engine.add_sugeno_rule(["low"], [linear([0.0], -100.0)]);
+        f_engine.add_sugeno_rule(["high"], [linear([-1.0], 0.0)]);
+
+        let before = f_engine.defuzz([50.0]);
+        f_engine.add_sugeno_rule(["high"], [linear([-1.0], 0.0)]);
+        // low and high both fire at 50, the duplicate doubles the weight of rule 2
+        assert_ne!(f_engine.defuzz([50.0]), before);
+
+        let analysis = f_engine.analyze_rules(&[[20.0], [50.0], [80.0]]);
+        assert_eq!(analysis.dominated, vec![]);
+        assert_eq!(analysis.conflicts, vec![(0, 1)]);
+        assert_eq!(analysis.unreachable, vec![]);
+        assert_eq!(analysis.suggested_removals, vec![]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mixing_rules() {
+        let rsi = LinguisticVar::new(
+            vec![(&triangular(20f64, 1.0, 20f64), "low")],
+            arange(0f64, 100f64, 1.0),
+        );
+
+        let mut f_engine = FuzzyEngine::new([rsi.clone()], [rsi]);
+        f_engine.add_rule(["low"], ["low"]);
+        f_engine.add_sugeno_rule(["low"], [linear([0.0], 100.0)]);
     }
 }
\ No newline at end of file
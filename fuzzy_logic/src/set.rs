@@ -36,7 +36,7 @@ impl LinguisticVar {
     }
 
     pub fn term(&self, name: &str) -> &FuzzySet {
-        match self.sets.iter().find(|x| x.name == name.to_string()) {
+        match self.sets.iter().find(|x| x.name == name) {
             Some(x) => x,
             None => panic![
                 "there're no fuzzy set name {} in this linguistic variable",
@@ -46,6 +46,16 @@ impl LinguisticVar {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Defuzzification {
+    #[default]
+    Centroid,
+    Bisector,
+    MeanOfMaximum,
+    SmallestOfMaximum,
+    LargestOfMaximum,
+}
+
 #[derive(Debug, Clone)]
 pub struct FuzzySet {
     pub name: String,
@@ -54,14 +64,14 @@ pub struct FuzzySet {
 }
 
 impl FuzzySet {
-    pub fn new(universe: &Vec<f64>, fuzzy_f: &dyn Shape, name: String) -> FuzzySet {
+    pub fn new(universe: &[f64], fuzzy_f: &dyn Shape, name: String) -> FuzzySet {
         let mut membership: Vec<f64> = vec![];
-        for i in 0..universe.len() {
-            membership.push(fuzzy_f.function(universe[i]));
+        for x in universe {
+            membership.push(fuzzy_f.function(*x));
         }
         FuzzySet {
             name: name.to_string(),
-            universe: universe.clone(),
+            universe: universe.to_vec(),
             membership,
         }
     }
@@ -98,6 +108,60 @@ impl FuzzySet {
         top_sum / bot_sum
     }
 
+    pub fn bisector_defuzz(&self) -> f64 {
+        let total = self.membership.iter().fold(0.0, |s, v| s + v);
+        if total == 0.0 {
+            return 0.0;
+        }
+        let mut area = 0.0;
+        for (x, v) in self.universe.iter().zip(self.membership.iter()) {
+            area += v;
+            if area >= total / 2.0 {
+                return *x;
+            }
+        }
+        self.universe[self.universe.len() - 1]
+    }
+
+    fn maximum_points(&self) -> Vec<f64> {
+        let max = self.membership.iter().fold(0.0, |m: f64, v| m.max(*v));
+        if max == 0.0 {
+            return vec![];
+        }
+        self.universe
+            .iter()
+            .zip(self.membership.iter())
+            .filter(|(_, v)| **v == max)
+            .map(|(x, _)| *x)
+            .collect()
+    }
+
+    pub fn mean_of_maximum(&self) -> f64 {
+        let points = self.maximum_points();
+        if points.is_empty() {
+            return 0.0;
+        }
+        points.iter().sum::<f64>() / points.len() as f64
+    }
+
+    pub fn smallest_of_maximum(&self) -> f64 {
+        *self.maximum_points().first().unwrap_or(&0.0)
+    }
+
+    pub fn largest_of_maximum(&self) -> f64 {
+        *self.maximum_points().last().unwrap_or(&0.0)
+    }
+
+    pub fn defuzz(&self, method: Defuzzification) -> f64 {
+        match method {
+            Defuzzification::Centroid => self.centroid_defuzz(),
+            Defuzzification::Bisector => self.bisector_defuzz(),
+            Defuzzification::MeanOfMaximum => self.mean_of_maximum(),
+            Defuzzification::SmallestOfMaximum => self.smallest_of_maximum(),
+            Defuzzification::LargestOfMaximum => self.largest_of_maximum(),
+        }
+    }
+
     pub fn min(&self, input: f64, name: String) -> FuzzySet {
         let mut membership: Vec<f64> = vec![];
         for i in 0..self.membership.len() {
@@ -188,4 +252,23 @@ mod tests {
         assert_eq!(var1.term("normal").degree_of(5.0), 0.8);
         assert_eq!(var1.term("weak").degree_of(3.0), 0.8);
     }
+
+    #[test]
+    fn test_defuzz() {
+        let s1 = FuzzySet::new(
+            &arange(0.0, 10.0, 1.0),
+            &trapezoidal(2.0, 4.0, 5.0, 9.0, 1.0),
+            "f1".into(),
+        );
+
+        assert_eq!(s1.defuzz(Defuzzification::Centroid), 5.125);
+        assert_eq!(s1.defuzz(Defuzzification::Bisector), 5.0);
+        assert_eq!(s1.defuzz(Defuzzification::MeanOfMaximum), 4.5);
+        assert_eq!(s1.defuzz(Defuzzification::SmallestOfMaximum), 4.0);
+        assert_eq!(s1.defuzz(Defuzzification::LargestOfMaximum), 5.0);
+
+        let empty = s1.min(0.0, "empty".into());
+        assert_eq!(empty.defuzz(Defuzzification::Bisector), 0.0);
+        assert_eq!(empty.defuzz(Defuzzification::MeanOfMaximum), 0.0);
+    }
 }
\ No newline at end of file